@@ -1,5 +1,6 @@
 use colored::*;
 use graphql_parser::Pos;
+use serde::Serialize;
 use std::fmt::{self, Write};
 
 #[derive(Debug, Eq, PartialEq, Ord, PartialOrd)]
@@ -7,45 +8,119 @@ pub struct Error<'doc> {
     pub(super) pos: Pos,
     pub(super) kind: ErrorKind<'doc>,
     pub(super) raw_schema: &'doc str,
+    pub(super) severity: Severity,
+    /// How many characters of the offending token to underline, starting at
+    /// `pos.column`. Defaults to `1` (just the `^` under the first character).
+    pub(super) token_len: usize,
+}
+
+/// How seriously a diagnostic should be taken.
+///
+/// An `Error` stops code generation; a `Warning` doesn't, but still gets
+/// printed so the user knows something in their schema looks off.
+#[derive(Debug, Eq, PartialEq, Ord, PartialOrd, Clone, Copy, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    Error,
+    Warning,
+}
+
+/// A machine-readable rendering of an [`Error`], for editors and CI to
+/// consume as data instead of scraping the ANSI-formatted [`Display`] output.
+#[derive(Debug, Serialize)]
+pub struct Diagnostic {
+    pub code: &'static str,
+    pub message: String,
+    pub notes: Option<String>,
+    pub severity: Severity,
+    pub pos: DiagnosticPos,
+}
+
+#[derive(Debug, Serialize)]
+pub struct DiagnosticPos {
+    pub line: usize,
+    pub column: usize,
+}
+
+/// A non-empty collection of [`Error`]s gathered while validating a schema,
+/// rendered one after another separated by a blank line.
+#[derive(Debug)]
+pub struct Errors<'doc>(pub(crate) Vec<Error<'doc>>);
+
+impl<'doc> Errors<'doc> {
+    /// The JSON-serializable [`Diagnostic`] for each error, in the same order
+    /// they were found in.
+    pub fn diagnostics(&self) -> Vec<Diagnostic> {
+        self.0.iter().map(Error::to_diagnostic).collect()
+    }
+}
+
+impl<'doc> fmt::Display for Errors<'doc> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for (i, error) in self.0.iter().enumerate() {
+            if i > 0 {
+                writeln!(f)?;
+            }
+            write!(f, "{}", error)?;
+        }
+        Ok(())
+    }
+}
+
+impl<'doc> Error<'doc> {
+    pub(super) fn new(pos: Pos, kind: ErrorKind<'doc>, raw_schema: &'doc str) -> Self {
+        let severity = kind.severity();
+        Error {
+            pos,
+            kind,
+            raw_schema,
+            severity,
+            token_len: 1,
+        }
+    }
+
+    /// Underline `token_len` characters starting at `pos.column` instead of
+    /// just the one under the caret. Use this when the offending token (a
+    /// name, a keyword, ...) is known to be longer than a single character.
+    pub(super) fn with_token_len(mut self, token_len: usize) -> Self {
+        self.token_len = token_len;
+        self
+    }
+
+    /// A JSON-serializable representation of this error, for callers that
+    /// want to drive a "problems" pane or grep for a stable error code
+    /// instead of parsing the human-formatted output.
+    pub fn to_diagnostic(&self) -> Diagnostic {
+        Diagnostic {
+            code: self.kind.code(),
+            message: self.kind.description(),
+            notes: self.kind.notes(),
+            severity: self.severity,
+            pos: DiagnosticPos {
+                line: self.pos.line,
+                column: self.pos.column,
+            },
+        }
+    }
 }
 
 impl<'a> fmt::Display for Error<'a> {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        // TODO: Handle lines that are really long and cause wrapping (screenshot on desktop)
         // TODO: Seems to be issues with multiline comments (screenshot on desktop)
 
         let schema_lines = self.raw_schema.lines().collect::<Vec<_>>();
 
-        let number_of_digits_in_line_count = number_of_digits(self.pos.line as i32);
-        let indent = 4;
+        let label = match self.severity {
+            Severity::Error => "error".bright_red(),
+            Severity::Warning => "warning".yellow(),
+        };
+        writeln!(f, "{label}: {kind}", kind = self.kind.description())?;
+        write_snippet(f, self.pos, &schema_lines, self.token_len, None)?;
 
-        writeln!(
-            f,
-            "{error}: {kind}",
-            error = "error".bright_red(),
-            kind = self.kind.description()
-        )?;
-        writeln!(
-            f,
-            "{indent} --> schema:{line}:{col}",
-            indent = "".indent(number_of_digits_in_line_count - 1),
-            line = self.pos.line,
-            col = self.pos.column
-        )?;
-        writeln!(f, "{} |", "".indent(number_of_digits_in_line_count))?;
-        writeln!(
-            f,
-            "{} |{}",
-            self.pos.line,
-            schema_lines[self.pos.line - 1].indent(indent),
-        )?;
-        writeln!(
-            f,
-            "{} |{}{}",
-            "".indent(number_of_digits_in_line_count),
-            "".indent(self.pos.column - 1 + indent),
-            "^".bright_red(),
-        )?;
+        if let Some((first_pos, label, token_len)) = self.kind.secondary() {
+            writeln!(f)?;
+            write_snippet(f, first_pos, &schema_lines, token_len, Some(label))?;
+        }
 
         if let Some(notes) = self.kind.notes() {
             writeln!(f)?;
@@ -58,15 +133,102 @@ impl<'a> fmt::Display for Error<'a> {
     }
 }
 
+/// Schema lines longer than this get a windowed view centered on the caret
+/// instead of being printed in full and wrapping the terminal.
+const MAX_SNIPPET_LINE_LEN: usize = 100;
+const SNIPPET_WINDOW_RADIUS: usize = 40;
+
+/// Normalize tabs (which render as more than one column wide but count as a
+/// single character in `Pos.column`) and, for very long lines, cut a window
+/// around `column` so the caret lines up with what's actually printed.
+fn prepare_snippet_line(line: &str, column: usize) -> (String, usize) {
+    let line = line.replace('\t', " ");
+
+    if line.chars().count() <= MAX_SNIPPET_LINE_LEN {
+        return (line, column);
+    }
+
+    let chars = line.chars().collect::<Vec<_>>();
+    let caret_index = column.saturating_sub(1).min(chars.len());
+    let start = caret_index.saturating_sub(SNIPPET_WINDOW_RADIUS);
+    let end = (caret_index + SNIPPET_WINDOW_RADIUS).min(chars.len());
+
+    let mut windowed = String::new();
+    if start > 0 {
+        windowed.push('…');
+    }
+    windowed.extend(&chars[start..end]);
+    if end < chars.len() {
+        windowed.push('…');
+    }
+
+    let leading_ellipsis = if start > 0 { 1 } else { 0 };
+    let column = caret_index - start + leading_ellipsis + 1;
+
+    (windowed, column)
+}
+
+fn write_snippet(
+    f: &mut fmt::Formatter,
+    pos: Pos,
+    schema_lines: &[&str],
+    token_len: usize,
+    label: Option<&str>,
+) -> fmt::Result {
+    let number_of_digits_in_line_count = number_of_digits(pos.line as i32);
+    let indent = 4;
+
+    if let Some(label) = label {
+        writeln!(
+            f,
+            "{}{}",
+            "".indent(number_of_digits_in_line_count - 1),
+            label,
+        )?;
+    }
+    writeln!(
+        f,
+        "{indent} --> schema:{line}:{col}",
+        indent = "".indent(number_of_digits_in_line_count - 1),
+        line = pos.line,
+        col = pos.column
+    )?;
+    writeln!(f, "{} |", "".indent(number_of_digits_in_line_count))?;
+
+    let (line, column) = prepare_snippet_line(schema_lines[pos.line - 1], pos.column);
+
+    // The windowed line may have been truncated, so never underline past
+    // what's actually printed.
+    let visible_len = line.chars().count().saturating_sub(column - 1);
+    let token_len = token_len.max(1).min(visible_len.max(1));
+
+    writeln!(f, "{} |{}", pos.line, line.as_str().indent(indent))?;
+    writeln!(
+        f,
+        "{} |{}{}",
+        "".indent(number_of_digits_in_line_count),
+        "".indent(column - 1 + indent),
+        "^".repeat(token_len).bright_red(),
+    )?;
+
+    Ok(())
+}
+
 #[derive(Debug, Eq, PartialEq, Ord, PartialOrd)]
 pub enum ErrorKind<'doc> {
     DateTimeScalarNotDefined,
     DateScalarNotDefined,
     DirectivesNotSupported,
+    DuplicateDefinition {
+        name: &'doc str,
+        kind: &'static str,
+        first_pos: Pos,
+    },
     NoQueryType,
     NonnullableFieldWithDefaultValue,
     NullDefaultValue,
     ObjectArgumentWithDefaultValue,
+    ReservedNameUsed { name: &'doc str, kind: &'static str },
     SubscriptionsNotSupported,
     TypeExtensionNotSupported,
     UnionFieldTypeMismatch {
@@ -77,6 +239,7 @@ pub enum ErrorKind<'doc> {
         type_b: &'doc str,
         field_type_b: &'doc str,
     },
+    UnreachableType { name: &'doc str },
     UnsupportedAttribute(&'doc str),
     UnsupportedAttributePair(&'doc str, &'doc str),
     VariableDefaultValue,
@@ -88,6 +251,9 @@ impl<'doc> ErrorKind<'doc> {
             ErrorKind::DateTimeScalarNotDefined => "You have to define a custom scalar called `DateTime` to use this type".to_string(),
             ErrorKind::DateScalarNotDefined => "You have to define a custom scalar called `Date` to use this type".to_string(),
             ErrorKind::DirectivesNotSupported => "Directives are currently not supported".to_string(),
+            ErrorKind::DuplicateDefinition { name, kind, first_pos: _ } => {
+                format!("`{}` is defined more than once as a {}", name, kind)
+            }
             ErrorKind::SubscriptionsNotSupported => "Subscriptions are currently not supported".to_string(),
             ErrorKind::NoQueryType => "Schema doesn't have root a Query type".to_string(),
             ErrorKind::NonnullableFieldWithDefaultValue => {
@@ -105,6 +271,9 @@ impl<'doc> ErrorKind<'doc> {
             ErrorKind::NullDefaultValue => {
                 "Having a default argument value of `null` is not supported. Use a nullable type instead".to_string()
             }
+            ErrorKind::ReservedNameUsed { name, kind: _ } => {
+                format!("`{}` is a reserved name; names beginning with `__` are reserved by GraphQL for introspection", name)
+            }
             ErrorKind::VariableDefaultValue => {
                 "Default arguments cannot refer to variables".to_string()
             }
@@ -114,6 +283,9 @@ impl<'doc> ErrorKind<'doc> {
             ErrorKind::UnionFieldTypeMismatch { union_name, field_name: _, type_a: _, type_b: _, field_type_a: _, field_type_b: _ } => {
                 format!("Error while generating `QueryTrail` for union `{}`", union_name)
             }
+            ErrorKind::UnreachableType { name } => {
+                format!("`{}` is never reachable from `Query` or `Mutation`", name)
+            }
         }
     }
 
@@ -141,9 +313,62 @@ impl<'doc> ErrorKind<'doc> {
             ErrorKind::DateScalarNotDefined => {
                 Some("Insert `scalar Date` into your schema".to_string())
             }
+            ErrorKind::ReservedNameUsed { name, kind } => Some(format!(
+                "`{}` is a {} name, but names starting with `__` are reserved for GraphQL's introspection system",
+                name, kind
+            )),
+            ErrorKind::UnreachableType { name } => Some(format!(
+                "`{}` isn't used by any field, argument, interface or union reachable from `Query` or `Mutation`\nIt will still be included in the generated code, which is probably not what you want",
+                name
+            )),
+            _ => None,
+        }
+    }
+
+    /// A secondary source position to render beneath the primary one, along
+    /// with the label to print above it (e.g. "first defined here") and how
+    /// many characters of it to underline.
+    fn secondary(&self) -> Option<(Pos, &'static str, usize)> {
+        match self {
+            ErrorKind::DuplicateDefinition {
+                name, first_pos, ..
+            } => Some((*first_pos, "first defined here", name.len())),
             _ => None,
         }
     }
+
+    /// Whether this should stop code generation or is merely advisory.
+    fn severity(&self) -> Severity {
+        match self {
+            ErrorKind::UnreachableType { .. } => Severity::Warning,
+            _ => Severity::Error,
+        }
+    }
+
+    /// A stable, grep-able identifier for this kind of error.
+    ///
+    /// These codes are part of the public API: once assigned they don't
+    /// change, so tooling can match on them across crate versions.
+    fn code(&self) -> &'static str {
+        match self {
+            ErrorKind::NoQueryType => "E0001",
+            ErrorKind::DirectivesNotSupported => "E0002",
+            ErrorKind::SubscriptionsNotSupported => "E0003",
+            ErrorKind::TypeExtensionNotSupported => "E0004",
+            ErrorKind::UnsupportedAttribute(_) => "E0005",
+            ErrorKind::UnsupportedAttributePair(_, _) => "E0006",
+            ErrorKind::UnionFieldTypeMismatch { .. } => "E0007",
+            ErrorKind::NonnullableFieldWithDefaultValue => "E0008",
+            ErrorKind::NullDefaultValue => "E0009",
+            ErrorKind::ObjectArgumentWithDefaultValue => "E0010",
+            ErrorKind::VariableDefaultValue => "E0011",
+            ErrorKind::DateTimeScalarNotDefined => "E0012",
+            ErrorKind::DateScalarNotDefined => "E0013",
+            ErrorKind::ReservedNameUsed { .. } => "E0014",
+            ErrorKind::DuplicateDefinition { .. } => "E0015",
+            ErrorKind::UnreachableType { .. } => "E0016",
+        }
+    }
 }
 
 trait Indent {
@@ -187,4 +412,36 @@ mod test {
         assert_eq!(2, number_of_digits(10));
         assert_eq!(7, number_of_digits(1_000_000));
     }
+
+    #[test]
+    fn prepare_snippet_line_normalizes_tabs() {
+        let (line, column) = prepare_snippet_line("\tfoo: String", 2);
+
+        assert_eq!(line, " foo: String");
+        assert_eq!(column, 2);
+        assert_eq!(line.chars().nth(column - 1), Some('f'));
+    }
+
+    #[test]
+    fn prepare_snippet_line_windows_long_lines_around_the_caret() {
+        let prefix = "a".repeat(60);
+        let suffix = "b".repeat(60);
+        let line = format!("{}HERE{}", prefix, suffix);
+        let column = 61; // 1-indexed position of the 'H' in "HERE"
+
+        let (windowed, new_column) = prepare_snippet_line(&line, column);
+
+        assert!(windowed.len() < line.len());
+        assert!(windowed.starts_with('…'));
+        assert!(windowed.ends_with('…'));
+        assert_eq!(windowed.chars().nth(new_column - 1), Some('H'));
+    }
+
+    #[test]
+    fn prepare_snippet_line_leaves_short_lines_alone() {
+        let (line, column) = prepare_snippet_line("foo: String", 1);
+
+        assert_eq!(line, "foo: String");
+        assert_eq!(column, 1);
+    }
 }
@@ -0,0 +1,5 @@
+pub(crate) mod error;
+pub(crate) mod validation;
+
+pub use self::error::{Error, Errors};
+pub(crate) use self::validation::validate;
@@ -0,0 +1,564 @@
+use crate::ast_pass::error::{Error, ErrorKind, Errors};
+use graphql_parser::schema::{Definition, Document, Field, Type, TypeDefinition};
+use graphql_parser::Pos;
+use std::collections::{HashMap, HashSet};
+
+/// Custom scalars that are allowed to go unused in a schema because they're
+/// built in to the types the generated code produces, not something the user
+/// necessarily references from a field.
+const ALWAYS_REACHABLE_SCALARS: &[&str] = &["Date", "DateTime"];
+
+/// Run every validation pass over `doc` and collect all the errors they find,
+/// instead of stopping at the first one.
+pub(crate) fn validate<'doc>(
+    doc: &'doc Document,
+    raw_schema: &'doc str,
+) -> Result<(), Errors<'doc>> {
+    let mut diagnostics = Diagnostics::new();
+
+    diagnostics.extend(validate_reserved_names(doc, raw_schema));
+    diagnostics.extend(validate_duplicate_definitions(doc, raw_schema));
+    diagnostics.extend(validate_unreachable_types(doc, raw_schema));
+
+    diagnostics.into_result()
+}
+
+/// Accumulates errors from each validation pass so a user sees every problem
+/// in their schema at once, rather than fixing them one compile at a time.
+#[derive(Debug, Default)]
+struct Diagnostics<'doc> {
+    errors: Vec<Error<'doc>>,
+}
+
+impl<'doc> Diagnostics<'doc> {
+    fn new() -> Self {
+        Diagnostics { errors: Vec::new() }
+    }
+
+    fn extend(&mut self, errors: Vec<Error<'doc>>) {
+        self.errors.extend(errors);
+    }
+
+    fn into_result(self) -> Result<(), Errors<'doc>> {
+        if self.errors.is_empty() {
+            Ok(())
+        } else {
+            Err(Errors(self.errors))
+        }
+    }
+}
+
+/// Reject any type, field, argument, or enum value whose name begins with `__`.
+///
+/// GraphQL reserves the `__` prefix for its own introspection machinery, so a
+/// schema that declares its own `__foo` would collide with that.
+pub(super) fn validate_reserved_names<'doc>(
+    doc: &'doc Document,
+    raw_schema: &'doc str,
+) -> Vec<Error<'doc>> {
+    let mut errors = Vec::new();
+
+    for def in &doc.definitions {
+        if let Definition::TypeDefinition(type_def) = def {
+            check_type_definition(type_def, raw_schema, &mut errors);
+        }
+    }
+
+    errors
+}
+
+fn check_type_definition<'doc>(
+    type_def: &'doc TypeDefinition,
+    raw_schema: &'doc str,
+    errors: &mut Vec<Error<'doc>>,
+) {
+    match type_def {
+        TypeDefinition::Scalar(scalar) => {
+            check_name(&scalar.name, scalar.position, "type", raw_schema, errors);
+        }
+        TypeDefinition::Object(object) => {
+            check_name(&object.name, object.position, "type", raw_schema, errors);
+            check_fields(&object.fields, raw_schema, errors);
+        }
+        TypeDefinition::Interface(interface) => {
+            check_name(&interface.name, interface.position, "type", raw_schema, errors);
+            check_fields(&interface.fields, raw_schema, errors);
+        }
+        TypeDefinition::Union(union_) => {
+            check_name(&union_.name, union_.position, "type", raw_schema, errors);
+        }
+        TypeDefinition::Enum(enum_) => {
+            check_name(&enum_.name, enum_.position, "type", raw_schema, errors);
+            for value in &enum_.values {
+                check_name(&value.name, value.position, "enum value", raw_schema, errors);
+            }
+        }
+        TypeDefinition::InputObject(input) => {
+            check_name(&input.name, input.position, "type", raw_schema, errors);
+            for field in &input.fields {
+                check_name(&field.name, field.position, "field", raw_schema, errors);
+            }
+        }
+    }
+}
+
+fn check_fields<'doc>(fields: &'doc [Field], raw_schema: &'doc str, errors: &mut Vec<Error<'doc>>) {
+    for field in fields {
+        check_name(&field.name, field.position, "field", raw_schema, errors);
+        for arg in &field.arguments {
+            check_name(&arg.name, arg.position, "argument", raw_schema, errors);
+        }
+    }
+}
+
+fn check_name<'doc>(
+    name: &'doc str,
+    pos: Pos,
+    kind: &'static str,
+    raw_schema: &'doc str,
+    errors: &mut Vec<Error<'doc>>,
+) {
+    if name.starts_with("__") {
+        errors.push(
+            Error::new(pos, ErrorKind::ReservedNameUsed { name, kind }, raw_schema)
+                .with_token_len(name.len()),
+        );
+    }
+}
+
+/// Find types, fields, enum values and union members that are defined more
+/// than once in the same scope.
+///
+/// `graphql_parser` doesn't keep a `Pos` per union member, only for the union
+/// itself, so a duplicate member is reported at the union's own `Pos` rather
+/// than at the member's.
+pub(super) fn validate_duplicate_definitions<'doc>(
+    doc: &'doc Document,
+    raw_schema: &'doc str,
+) -> Vec<Error<'doc>> {
+    let mut errors = Vec::new();
+    let mut top_level = Scope::new();
+
+    for def in &doc.definitions {
+        if let Definition::TypeDefinition(type_def) = def {
+            match type_def {
+                TypeDefinition::Scalar(inner) => {
+                    top_level.check(&inner.name, inner.position, "type", raw_schema, &mut errors)
+                }
+                TypeDefinition::Object(inner) => {
+                    top_level.check(&inner.name, inner.position, "type", raw_schema, &mut errors)
+                }
+                TypeDefinition::Interface(inner) => {
+                    top_level.check(&inner.name, inner.position, "type", raw_schema, &mut errors)
+                }
+                TypeDefinition::Union(inner) => {
+                    top_level.check(&inner.name, inner.position, "type", raw_schema, &mut errors)
+                }
+                TypeDefinition::Enum(inner) => {
+                    top_level.check(&inner.name, inner.position, "type", raw_schema, &mut errors)
+                }
+                TypeDefinition::InputObject(inner) => {
+                    top_level.check(&inner.name, inner.position, "type", raw_schema, &mut errors)
+                }
+            }
+
+            match type_def {
+                TypeDefinition::Object(object) => {
+                    check_duplicate_fields(&object.fields, raw_schema, &mut errors);
+                }
+                TypeDefinition::Interface(interface) => {
+                    check_duplicate_fields(&interface.fields, raw_schema, &mut errors);
+                }
+                TypeDefinition::InputObject(input) => {
+                    let mut scope = Scope::new();
+                    for field in &input.fields {
+                        scope.check(&field.name, field.position, "field", raw_schema, &mut errors);
+                    }
+                }
+                TypeDefinition::Enum(enum_) => {
+                    let mut scope = Scope::new();
+                    for value in &enum_.values {
+                        scope.check(
+                            &value.name,
+                            value.position,
+                            "enum value",
+                            raw_schema,
+                            &mut errors,
+                        );
+                    }
+                }
+                TypeDefinition::Union(union_) => {
+                    let mut scope = Scope::new();
+                    for member in &union_.types {
+                        scope.check(
+                            member,
+                            union_.position,
+                            "union member",
+                            raw_schema,
+                            &mut errors,
+                        );
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+
+    errors
+}
+
+fn check_duplicate_fields<'doc>(
+    fields: &'doc [Field],
+    raw_schema: &'doc str,
+    errors: &mut Vec<Error<'doc>>,
+) {
+    let mut scope = Scope::new();
+    for field in fields {
+        scope.check(&field.name, field.position, "field", raw_schema, errors);
+
+        let mut arg_scope = Scope::new();
+        for arg in &field.arguments {
+            arg_scope.check(&arg.name, arg.position, "argument", raw_schema, errors);
+        }
+    }
+}
+
+/// A set of names seen so far within a single scope (a document, an object's
+/// fields, an enum's values, ...), used to spot the second occurrence of a
+/// name and the `Pos` it was first seen at.
+struct Scope<'doc> {
+    seen: HashMap<&'doc str, Pos>,
+}
+
+impl<'doc> Scope<'doc> {
+    fn new() -> Self {
+        Scope {
+            seen: HashMap::new(),
+        }
+    }
+
+    fn check(
+        &mut self,
+        name: &'doc str,
+        pos: Pos,
+        kind: &'static str,
+        raw_schema: &'doc str,
+        errors: &mut Vec<Error<'doc>>,
+    ) {
+        if let Some(&first_pos) = self.seen.get(name) {
+            errors.push(
+                Error::new(
+                    pos,
+                    ErrorKind::DuplicateDefinition {
+                        name,
+                        kind,
+                        first_pos,
+                    },
+                    raw_schema,
+                )
+                .with_token_len(name.len()),
+            );
+        } else {
+            self.seen.insert(name, pos);
+        }
+    }
+}
+
+/// Warn about types that can never be reached by walking the schema starting
+/// from `Query` and `Mutation`.
+///
+/// Such types still get code generated for them, which is almost always a
+/// sign the schema has a typo or some dead code that should be removed.
+pub(super) fn validate_unreachable_types<'doc>(
+    doc: &'doc Document,
+    raw_schema: &'doc str,
+) -> Vec<Error<'doc>> {
+    let mut definitions = HashMap::new();
+    for def in &doc.definitions {
+        if let Definition::TypeDefinition(type_def) = def {
+            definitions.insert(type_def_name(type_def), type_def);
+        }
+    }
+
+    let mut reachable = HashSet::new();
+    let mut worklist = Vec::new();
+
+    for root in root_type_names(doc) {
+        if reachable.insert(root) {
+            worklist.push(root);
+        }
+    }
+
+    while let Some(name) = worklist.pop() {
+        let type_def = match definitions.get(name) {
+            Some(type_def) => *type_def,
+            None => continue,
+        };
+
+        for referenced in referenced_type_names(type_def) {
+            if reachable.insert(referenced) {
+                worklist.push(referenced);
+            }
+        }
+    }
+
+    // Walk `doc.definitions` again, rather than the `definitions` map, so the
+    // warnings come out in source order instead of in `HashMap` order.
+    let mut errors = Vec::new();
+    for def in &doc.definitions {
+        if let Definition::TypeDefinition(type_def) = def {
+            let name = type_def_name(type_def);
+            if reachable.contains(name) || ALWAYS_REACHABLE_SCALARS.contains(&name) {
+                continue;
+            }
+
+            errors.push(
+                Error::new(
+                    type_def_position(type_def),
+                    ErrorKind::UnreachableType { name },
+                    raw_schema,
+                )
+                .with_token_len(name.len()),
+            );
+        }
+    }
+    errors
+}
+
+/// The names of the root `Query` and (if present) `Mutation` types, taken
+/// from an explicit `schema { ... }` definition or, failing that, the
+/// conventional `Query`/`Mutation` type names.
+fn root_type_names(doc: &Document) -> Vec<&str> {
+    for def in &doc.definitions {
+        if let Definition::SchemaDefinition(schema) = def {
+            let mut names = Vec::new();
+            if let Some(query) = &schema.query {
+                names.push(query.as_str());
+            }
+            if let Some(mutation) = &schema.mutation {
+                names.push(mutation.as_str());
+            }
+            return names;
+        }
+    }
+
+    vec!["Query", "Mutation"]
+}
+
+fn type_def_name<'doc>(type_def: &'doc TypeDefinition) -> &'doc str {
+    match type_def {
+        TypeDefinition::Scalar(inner) => &inner.name,
+        TypeDefinition::Object(inner) => &inner.name,
+        TypeDefinition::Interface(inner) => &inner.name,
+        TypeDefinition::Union(inner) => &inner.name,
+        TypeDefinition::Enum(inner) => &inner.name,
+        TypeDefinition::InputObject(inner) => &inner.name,
+    }
+}
+
+fn type_def_position(type_def: &TypeDefinition) -> Pos {
+    match type_def {
+        TypeDefinition::Scalar(inner) => inner.position,
+        TypeDefinition::Object(inner) => inner.position,
+        TypeDefinition::Interface(inner) => inner.position,
+        TypeDefinition::Union(inner) => inner.position,
+        TypeDefinition::Enum(inner) => inner.position,
+        TypeDefinition::InputObject(inner) => inner.position,
+    }
+}
+
+/// Every named type a definition's fields, arguments, interfaces or members
+/// refer to.
+fn referenced_type_names<'doc>(type_def: &'doc TypeDefinition) -> Vec<&'doc str> {
+    let mut names = Vec::new();
+
+    match type_def {
+        TypeDefinition::Object(object) => {
+            names.extend(object.implements_interfaces.iter().map(String::as_str));
+            for field in &object.fields {
+                names.push(named_type(&field.field_type));
+                for arg in &field.arguments {
+                    names.push(named_type(&arg.value_type));
+                }
+            }
+        }
+        TypeDefinition::Interface(interface) => {
+            for field in &interface.fields {
+                names.push(named_type(&field.field_type));
+                for arg in &field.arguments {
+                    names.push(named_type(&arg.value_type));
+                }
+            }
+        }
+        TypeDefinition::Union(union_) => {
+            names.extend(union_.types.iter().map(String::as_str));
+        }
+        TypeDefinition::InputObject(input) => {
+            for field in &input.fields {
+                names.push(named_type(&field.value_type));
+            }
+        }
+        TypeDefinition::Enum(_) | TypeDefinition::Scalar(_) => {}
+    }
+
+    names
+}
+
+fn named_type(ty: &Type) -> &str {
+    match ty {
+        Type::NamedType(name) => name,
+        Type::ListType(inner) => named_type(inner),
+        Type::NonNullType(inner) => named_type(inner),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use graphql_parser::parse_schema;
+
+    #[test]
+    fn rejects_type_names_starting_with_double_underscore() {
+        let raw_schema = "type __Foo { id: ID }";
+        let doc = parse_schema(raw_schema).unwrap();
+
+        let errors = validate_reserved_names(&doc, raw_schema);
+
+        assert_eq!(errors.len(), 1);
+        match &errors[0].kind {
+            ErrorKind::ReservedNameUsed { name, kind } => {
+                assert_eq!(*name, "__Foo");
+                assert_eq!(*kind, "type");
+            }
+            other => panic!("expected ReservedNameUsed, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn rejects_field_and_argument_names_starting_with_double_underscore() {
+        let raw_schema = r#"
+            type Foo {
+                __bar(__baz: ID): String
+            }
+        "#;
+        let doc = parse_schema(raw_schema).unwrap();
+
+        let errors = validate_reserved_names(&doc, raw_schema);
+
+        let kinds = errors
+            .iter()
+            .map(|error| match &error.kind {
+                ErrorKind::ReservedNameUsed { kind, .. } => *kind,
+                other => panic!("expected ReservedNameUsed, got {:?}", other),
+            })
+            .collect::<Vec<_>>();
+
+        assert_eq!(kinds, vec!["field", "argument"]);
+    }
+
+    #[test]
+    fn allows_names_without_the_reserved_prefix() {
+        let raw_schema = "type Foo { bar: String }";
+        let doc = parse_schema(raw_schema).unwrap();
+
+        assert!(validate_reserved_names(&doc, raw_schema).is_empty());
+    }
+
+    #[test]
+    fn reports_duplicate_fields_with_both_positions() {
+        let raw_schema = "type Foo {\n    bar: String\n    bar: Int\n}";
+        let doc = parse_schema(raw_schema).unwrap();
+
+        let errors = validate_duplicate_definitions(&doc, raw_schema);
+
+        assert_eq!(errors.len(), 1);
+        match &errors[0].kind {
+            ErrorKind::DuplicateDefinition {
+                name,
+                kind,
+                first_pos,
+            } => {
+                assert_eq!(*name, "bar");
+                assert_eq!(*kind, "field");
+                assert_eq!(first_pos.line, 2);
+            }
+            other => panic!("expected DuplicateDefinition, got {:?}", other),
+        }
+        assert_eq!(errors[0].pos.line, 3);
+    }
+
+    #[test]
+    fn reports_duplicate_enum_values() {
+        let raw_schema = "enum Foo {\n    BAR\n    BAR\n}";
+        let doc = parse_schema(raw_schema).unwrap();
+
+        let errors = validate_duplicate_definitions(&doc, raw_schema);
+
+        assert_eq!(errors.len(), 1);
+        match &errors[0].kind {
+            ErrorKind::DuplicateDefinition { name, kind, .. } => {
+                assert_eq!(*name, "BAR");
+                assert_eq!(*kind, "enum value");
+            }
+            other => panic!("expected DuplicateDefinition, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn reports_duplicate_union_members() {
+        let raw_schema = "union Foo = A | A\ntype A { id: ID }";
+        let doc = parse_schema(raw_schema).unwrap();
+
+        let errors = validate_duplicate_definitions(&doc, raw_schema);
+
+        assert_eq!(errors.len(), 1);
+        match &errors[0].kind {
+            ErrorKind::DuplicateDefinition { name, kind, .. } => {
+                assert_eq!(*name, "A");
+                assert_eq!(*kind, "union member");
+            }
+            other => panic!("expected DuplicateDefinition, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn warns_about_types_unreachable_from_query_or_mutation() {
+        let raw_schema = r#"
+            type Query {
+                foo: Foo
+            }
+
+            type Foo {
+                id: ID
+            }
+
+            type Unreachable {
+                id: ID
+            }
+        "#;
+        let doc = parse_schema(raw_schema).unwrap();
+
+        let errors = validate_unreachable_types(&doc, raw_schema);
+
+        assert_eq!(errors.len(), 1);
+        match &errors[0].kind {
+            ErrorKind::UnreachableType { name } => assert_eq!(*name, "Unreachable"),
+            other => panic!("expected UnreachableType, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn does_not_warn_about_date_and_datetime_scalars() {
+        let raw_schema = r#"
+            type Query {
+                id: ID
+            }
+
+            scalar Date
+            scalar DateTime
+        "#;
+        let doc = parse_schema(raw_schema).unwrap();
+
+        assert!(validate_unreachable_types(&doc, raw_schema).is_empty());
+    }
+}